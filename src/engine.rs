@@ -1,12 +1,60 @@
 use std::{
+    collections::{HashMap, VecDeque},
+    num::NonZeroUsize,
     ops::{Index, IndexMut, Not},
+    sync::Arc,
     time::{Duration, Instant},
 };
 
-use rand::prelude::SliceRandom;
+use rand::{prelude::SliceRandom, rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
 use shakmaty::{CastlingMode, Color, Move, Outcome, Position, Setup};
 
 use crate::board::{Bughouse, BughousePositionError};
+use crate::heuristic::{Heuristic, ZeroHeuristic};
+
+// A game-theoretically proven outcome, from the perspective of the node's
+// `side_that_moved` (i.e. the same perspective as its `wins` tally).
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum ProvenValue {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl ProvenValue {
+    fn of(outcome: Outcome, side_that_moved: Color) -> ProvenValue {
+        match outcome {
+            Outcome::Decisive { winner } if winner == side_that_moved => ProvenValue::Win,
+            Outcome::Decisive { .. } => ProvenValue::Loss,
+            Outcome::Draw => ProvenValue::Draw,
+        }
+    }
+
+    // Inverse of `of`: the concrete game outcome implied by this proven
+    // value from `side_that_moved`'s perspective.
+    fn to_outcome(self, side_that_moved: Color) -> Outcome {
+        match self {
+            ProvenValue::Win => Outcome::Decisive {
+                winner: side_that_moved,
+            },
+            ProvenValue::Loss => Outcome::Decisive {
+                winner: side_that_moved.not(),
+            },
+            ProvenValue::Draw => Outcome::Draw,
+        }
+    }
+}
+
+// All-Moves-As-First statistics for one of a node's children, indexed by
+// that child's position in `Node::children`. Credits a move with a
+// simulation's result even when it wasn't the child actually descended
+// into, as long as the same side played it later in that simulation.
+#[derive(Copy, Clone, Default)]
+struct AmafStats {
+    wins: f32,
+    visits: i32,
+}
 
 struct Node {
     side_that_moved: Color,
@@ -15,6 +63,27 @@ struct Node {
     wins: f32,
     simulations: i32,
     children: Vec<NodeId>,
+    // Parallel to `children`: amaf[i] holds the AMAF statistics for
+    // children[i], gathered from simulations that played that move without
+    // necessarily selecting it here.
+    amaf: Vec<AmafStats>,
+    proven: Option<ProvenValue>,
+}
+
+impl Node {
+    fn root(position: Bughouse) -> Node {
+        let side_that_moved = position.turn().not();
+        Node {
+            last_move: None,
+            side_that_moved,
+            position,
+            wins: 0f32,
+            simulations: 0,
+            children: vec![],
+            amaf: vec![],
+            proven: None,
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -22,6 +91,8 @@ struct NodeId(usize);
 
 struct Tree {
     nodes: Vec<Node>,
+    heuristic: Arc<dyn Heuristic>,
+    rng: StdRng,
 }
 impl Index<NodeId> for Tree {
     type Output = Node;
@@ -37,8 +108,12 @@ impl IndexMut<NodeId> for Tree {
 }
 
 impl Tree {
-    fn new(root: Node) -> Tree {
-        Tree { nodes: vec![root] }
+    fn new(root: Node, heuristic: Arc<dyn Heuristic>, seed: Option<u64>) -> Tree {
+        Tree {
+            nodes: vec![root],
+            heuristic,
+            rng: seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64),
+        }
     }
     fn push_node(&mut self, node: Node) -> NodeId {
         let idx = self.nodes.len();
@@ -49,23 +124,51 @@ impl Tree {
     fn select_next(&self, node_id: NodeId) -> Option<NodeId> {
         let node = &self[node_id];
         let exploration_constant = 1.414; // sqrt(2) is theoretically ideal, but in practice this value is adjusted to maximize strength
-        let uct = |child_id: NodeId| {
+        // RAVE blending constant: tunes how fast the weight shifts from the
+        // AMAF estimate to the real win rate as real visits accumulate.
+        let rave_b = 0.0009f32;
+        let uct = |child_id: NodeId, amaf: AmafStats| {
             let child = &self[child_id];
-            if child.simulations == 0 {
-                // Suggestions from around the internet say that the UCT score for unvisited nodes should be very high
-                f32::MAX
-            } else {
-                child.wins / child.simulations as f32
-                    + exploration_constant
-                        * ((node.simulations as f32).ln() / child.simulations as f32).sqrt()
+            match child.proven {
+                // Never walk into a child that's already proven lost -- it
+                // cannot get any better no matter how much more we search it.
+                Some(ProvenValue::Loss) => f32::NEG_INFINITY,
+                // A proven win is an immediate, decisive choice.
+                Some(ProvenValue::Win) => f32::INFINITY,
+                Some(ProvenValue::Draw) | None if child.simulations == 0 && amaf.visits == 0 => {
+                    // Suggestions from around the internet say that the UCT score for unvisited nodes should be very high
+                    f32::MAX
+                }
+                Some(ProvenValue::Draw) | None => {
+                    let sims = child.simulations as f32;
+                    let amaf_visits = amaf.visits as f32;
+                    let beta = amaf_visits
+                        / (amaf_visits + sims + 4.0 * sims * amaf_visits * rave_b * rave_b);
+                    let real_rate = if child.simulations == 0 {
+                        0f32
+                    } else {
+                        child.wins / sims
+                    };
+                    let amaf_rate = if amaf.visits == 0 {
+                        0f32
+                    } else {
+                        amaf.wins / amaf_visits
+                    };
+                    (1.0 - beta) * real_rate
+                        + beta * amaf_rate
+                        + exploration_constant
+                            * ((node.simulations as f32).ln() / sims.max(1.0)).sqrt()
+                }
             }
         };
         node.children
             .iter()
+            .enumerate()
             .fold(
                 (None, -1f32),
-                |(highest_uct_child, highest_uct): (Option<NodeId>, f32), &child_id| {
-                    let uct_score = uct(child_id);
+                |(highest_uct_child, highest_uct): (Option<NodeId>, f32),
+                 (child_index, &child_id)| {
+                    let uct_score = uct(child_id, node.amaf[child_index]);
                     if uct_score > highest_uct {
                         (Some(child_id), uct_score)
                     } else {
@@ -76,32 +179,47 @@ impl Tree {
             .0
     }
 
-    // Selects an array of nodes from the root down to a leaf
+    // Selects an array of nodes from the root down to a leaf. A node whose
+    // outcome is already proven is treated as a leaf: it has nothing left to
+    // learn from further search.
     fn select_branch(&self, root: NodeId) -> Vec<NodeId> {
         let mut branch = vec![root];
-        while let Some(next) = self.select_next(*branch.last().unwrap()) {
-            branch.push(next);
+        while self[*branch.last().unwrap()].proven.is_none() {
+            match self.select_next(*branch.last().unwrap()) {
+                Some(next) => branch.push(next),
+                None => break,
+            }
         }
         branch
     }
 
     fn expand_tree(&mut self, leaf_id: NodeId) {
-        let node = &mut self[leaf_id];
-        let children: Vec<_> = node
-            .position
+        let position = self[leaf_id].position.clone();
+        let side_that_moved = self[leaf_id].side_that_moved;
+        let heuristic = &self.heuristic;
+        let children: Vec<_> = position
             .legal_moves()
             .iter()
-            .map(|legal_move| Node {
-                last_move: Some(legal_move.clone()),
-                side_that_moved: node.side_that_moved.not(),
-                position: node
-                    .position
+            .map(|legal_move| {
+                let (wins, simulations) = heuristic.prior(&position, legal_move);
+                let child_side_that_moved = side_that_moved.not();
+                let child_position = position
                     .clone()
-                    .play(&legal_move)
-                    .expect("Illegal move played from legal move list"),
-                wins: 0f32,
-                simulations: 0,
-                children: vec![],
+                    .play(legal_move)
+                    .expect("Illegal move played from legal move list");
+                let proven = child_position
+                    .outcome()
+                    .map(|outcome| ProvenValue::of(outcome, child_side_that_moved));
+                Node {
+                    last_move: Some(legal_move.clone()),
+                    side_that_moved: child_side_that_moved,
+                    position: child_position,
+                    wins,
+                    simulations,
+                    children: vec![],
+                    amaf: vec![],
+                    proven,
+                }
             })
             .collect();
         let children_ids: Vec<_> = children
@@ -109,21 +227,41 @@ impl Tree {
             .map(|node| self.push_node(node))
             .collect();
 
+        self[leaf_id]
+            .amaf
+            .extend(children_ids.iter().map(|_| AmafStats::default()));
         self[leaf_id].children.extend(children_ids);
     }
 
-    fn simulate(position: &Bughouse) -> Outcome {
+    // Plays random moves, weighted by the heuristic's `move_weight`, to the
+    // end of the game, and
+    // records who played each move along the way so the caller can later
+    // credit those moves via AMAF, not just the child actually descended
+    // into.
+    fn simulate(&mut self, position: &Bughouse) -> (Outcome, Vec<(Color, Move)>) {
         let mut simulation_board = position.clone();
+        let mut moves_played = Vec::new();
+        let heuristic = Arc::clone(&self.heuristic);
         loop {
-            if let Some(random_move) = simulation_board
-                .legal_moves()
-                .choose(&mut rand::thread_rng())
-            {
+            let legal_moves = simulation_board.legal_moves();
+            // `choose_weighted` errors out if every weight is zero (e.g. a
+            // heuristic that only scores checks/captures/drops above zero,
+            // applied to a quiet position with none of those). That's not a
+            // "no legal moves" situation, so fall back to an unweighted pick
+            // instead of treating it as one.
+            let chosen = legal_moves
+                .choose_weighted(&mut self.rng, |mv| {
+                    heuristic.move_weight(&simulation_board, mv)
+                })
+                .ok()
+                .or_else(|| legal_moves.choose(&mut self.rng));
+            if let Some(random_move) = chosen {
+                moves_played.push((simulation_board.turn(), random_move.clone()));
                 simulation_board = simulation_board
                     .play(random_move)
                     .expect("Illegal move played from legal move list");
             } else if let Some(outcome) = simulation_board.outcome() {
-                break outcome;
+                break (outcome, moves_played);
             } else {
                 panic!(
                     "No legal moves were found, but the game is not over (this should be impossible)"
@@ -132,54 +270,276 @@ impl Tree {
         }
     }
 
-    fn backpropagate(&mut self, branch: Vec<NodeId>, result: Outcome) {
-        for node_id in branch {
-            let node = &mut self[node_id];
-            node.wins += match result {
-                Outcome::Decisive { winner } => {
-                    if winner == node.side_that_moved {
-                        1f32
-                    } else {
-                        0f32
-                    }
+    // MCTS-Solver rule. A child's `proven` value is from the perspective of
+    // whoever moves into it -- the same player who is choosing among this
+    // node's children. So if any child is a proven win for its mover, that
+    // mover will simply play it, which makes this node a proven loss for
+    // *its* own mover (the opponent). Symmetrically, this node is a proven
+    // win only once every child is a proven loss for its mover, i.e. no
+    // matter which move the opponent picks, they lose. Otherwise it stays
+    // unproven. A node's own proven value, once set, never changes.
+    fn recompute_proven(&self, node_id: NodeId) -> Option<ProvenValue> {
+        let node = &self[node_id];
+        if node.proven.is_some() {
+            return node.proven;
+        }
+        if node.children.is_empty() {
+            return None;
+        }
+        if node
+            .children
+            .iter()
+            .any(|&child| self[child].proven == Some(ProvenValue::Win))
+        {
+            Some(ProvenValue::Loss)
+        } else if node
+            .children
+            .iter()
+            .all(|&child| self[child].proven == Some(ProvenValue::Loss))
+        {
+            Some(ProvenValue::Win)
+        } else {
+            None
+        }
+    }
+
+    fn win_value(result: Outcome, side: Color) -> f32 {
+        match result {
+            Outcome::Decisive { winner } => {
+                if winner == side {
+                    1f32
+                } else {
+                    0f32
                 }
-                Outcome::Draw => 0.5f32,
-            };
+            }
+            Outcome::Draw => 0.5f32,
+        }
+    }
+
+    // Updates real visit counts down the branch, then credits AMAF stats:
+    // for every node on the branch, every one of its children whose move
+    // was also played later in this simulation (by the same side) gets the
+    // simulation's result too, whether or not it was the child actually
+    // selected.
+    fn backpropagate(
+        &mut self,
+        branch: &[NodeId],
+        result: Outcome,
+        rollout_moves: &[(Color, Move)],
+    ) {
+        for &node_id in branch {
+            let node = &mut self[node_id];
+            node.wins += Self::win_value(result, node.side_that_moved);
             node.simulations += 1;
         }
+
+        let branch_moves = branch[1..].iter().map(|&node_id| {
+            let node = &self[node_id];
+            (
+                node.side_that_moved,
+                node.last_move.clone().expect("non-root node always has a last_move"),
+            )
+        });
+        let move_sequence: Vec<(Color, Move)> =
+            branch_moves.chain(rollout_moves.iter().cloned()).collect();
+
+        for (i, &node_id) in branch.iter().enumerate() {
+            if i >= move_sequence.len() {
+                continue; // nothing was played onward from this node in this simulation
+            }
+            let later_moves = &move_sequence[i..];
+            let children = self[node_id].children.clone();
+            for (child_index, child_id) in children.into_iter().enumerate() {
+                let child_side = self[child_id].side_that_moved;
+                let child_move = self[child_id]
+                    .last_move
+                    .clone()
+                    .expect("child always has a last_move");
+                if later_moves
+                    .iter()
+                    .any(|(color, mv)| *color == child_side && *mv == child_move)
+                {
+                    let value = Self::win_value(result, child_side);
+                    let amaf = &mut self[node_id].amaf[child_index];
+                    amaf.visits += 1;
+                    amaf.wins += value;
+                }
+            }
+        }
+
+        for &node_id in branch.iter().rev() {
+            self[node_id].proven = self.recompute_proven(node_id);
+        }
     }
 
+    // One MCTS iteration: select down to a not-yet-expanded (or proven)
+    // leaf, expand it exactly once if it turns out to be a live position,
+    // roll out from a single one of its new children, and backpropagate
+    // that one result up the branch, including the new child. Terminal
+    // leaves are scored directly from their `Outcome` instead of being
+    // expanded, since a checkmated or stalemated position has no legal
+    // moves to expand.
     pub fn execute_mcts(&mut self) {
         let root_id = NodeId(0);
         let mut branch = self.select_branch(root_id);
         let leaf = *branch.last().expect("Branch should not be empty");
+
+        // `select_branch` only stops here because `leaf` has no children
+        // yet, not because anyone has confirmed it's terminal -- in
+        // particular a freshly-created root never gets the chance to have
+        // its own position checked, since that normally happens in the
+        // *parent's* `expand_tree`. Check directly so a game that's
+        // already over doesn't get "expanded" into zero children and
+        // silently contribute nothing to the search.
+        if self[leaf].proven.is_none() {
+            if let Some(outcome) = self[leaf].position.outcome() {
+                self[leaf].proven = Some(ProvenValue::of(outcome, self[leaf].side_that_moved));
+            }
+        }
+
+        if let Some(proven) = self[leaf].proven {
+            let outcome = proven.to_outcome(self[leaf].side_that_moved);
+            self.backpropagate(&branch, outcome, &[]);
+            return;
+        }
+
         self.expand_tree(leaf);
-        if let Some(c) = self[leaf].children.choose(&mut rand::thread_rng()) {
-            let outcome = Tree::simulate(&self[*c].position);
-            branch.push(*c);
-            self.backpropagate(branch, outcome);
+        let children = self[leaf].children.clone();
+        if let Some(&c) = children.choose(&mut self.rng) {
+            let (outcome, rollout_moves) = match self[c].proven {
+                // The child itself is already a terminal position (e.g. a
+                // mate-in-one); there's nothing left to roll out.
+                Some(proven) => (proven.to_outcome(self[c].side_that_moved), vec![]),
+                None => {
+                    let position = self[c].position.clone();
+                    self.simulate(&position)
+                }
+            };
+            branch.push(c);
+            self.backpropagate(&branch, outcome, &rollout_moves);
+        }
+    }
+
+    fn root_position(&self) -> &Bughouse {
+        &self[NodeId(0)].position
+    }
+
+    // Makes the child that played `mv` the new root, discarding every node
+    // that isn't reachable from it (the replies to moves the opponent
+    // didn't make, mainly). Falls back to a fresh one-node tree if `mv` was
+    // never expanded from the current root.
+    fn play(&mut self, mv: &Move) {
+        let matching_child = self[NodeId(0)]
+            .children
+            .iter()
+            .copied()
+            .find(|&child_id| self[child_id].last_move.as_ref() == Some(mv));
+        match matching_child {
+            Some(new_root_id) => self.reroot(new_root_id),
+            None => {
+                let position = self
+                    .root_position()
+                    .clone()
+                    .play(mv)
+                    .expect("Illegal move played from legal move list");
+                self.nodes = vec![Node::root(position)];
+            }
         }
     }
 
-    pub fn best_move(&self) -> Option<Move> {
+    // Compacts the tree down to just the subtree reachable from
+    // `new_root_id`, which becomes NodeId(0). `nodes` is a flat `Vec`
+    // indexed by `NodeId`, so this walks the surviving subtree breadth
+    // first, copies each node into a fresh `Vec` in that order, and
+    // rewrites every `children` entry to its new index.
+    fn reroot(&mut self, new_root_id: NodeId) {
+        let mut order = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(new_root_id);
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            queue.extend(self[id].children.iter().copied());
+        }
+        let new_index_of: HashMap<usize, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(new_index, id)| (id.0, new_index))
+            .collect();
+
+        let mut old_nodes: Vec<Option<Node>> =
+            std::mem::take(&mut self.nodes).into_iter().map(Some).collect();
+        let mut new_nodes = Vec::with_capacity(order.len());
+        for id in &order {
+            let mut node = old_nodes[id.0]
+                .take()
+                .expect("each reachable node is visited exactly once");
+            node.children = node
+                .children
+                .iter()
+                .map(|child_id| NodeId(new_index_of[&child_id.0]))
+                .collect();
+            new_nodes.push(node);
+        }
+        self.nodes = new_nodes;
+    }
+
+    // Proven value and visit count of each of the root's children, in the
+    // same order they were expanded in. Trees built from the same root
+    // position expand their children in the same (deterministic) order, so
+    // these line up index-for-index across independently searched trees.
+    fn root_child_stats(&self) -> Vec<(Option<ProvenValue>, i32)> {
+        self[NodeId(0)]
+            .children
+            .iter()
+            .map(|&child_id| (self[child_id].proven, self[child_id].simulations))
+            .collect()
+    }
+
+    pub fn best_move(&mut self) -> Option<Move> {
         let root = &self[NodeId(0)];
-        let best_child_id = root.children.iter().fold(
-            None,
-            |most_visited_child_or_none: Option<NodeId>, next_child_id| {
-                if let Some(most_visited_child_id) = most_visited_child_or_none {
-                    let most_visited_child = &self[most_visited_child_id];
-                    let next_child = &self[*next_child_id];
-                    println!("This node was simulated {} times", next_child.simulations);
-                    if next_child.simulations > most_visited_child.simulations {
-                        Some(*next_child_id)
-                    } else {
-                        Some(most_visited_child_id)
-                    }
-                } else {
-                    Some(*next_child_id)
-                }
-            },
-        )?;
+        // A proven win settles the question outright, regardless of how
+        // many simulations the most-visited child has accumulated.
+        let proven_win = root
+            .children
+            .iter()
+            .find(|&&child_id| self[child_id].proven == Some(ProvenValue::Win));
+        // Collect visit counts up front so the fold below only needs
+        // `&mut self.rng` to break ties, not another borrow of `self`.
+        let visit_counts: Vec<(NodeId, i32)> = root
+            .children
+            .iter()
+            .map(|&child_id| (child_id, self[child_id].simulations))
+            .collect();
+        let best_child_id = proven_win.copied().or_else(|| {
+            visit_counts
+                .into_iter()
+                .fold(
+                    None,
+                    |most_visited_or_none: Option<(NodeId, i32)>, (next_id, next_sims)| {
+                        match most_visited_or_none {
+                            Some((most_visited_id, most_visited_sims)) => {
+                                match next_sims.cmp(&most_visited_sims) {
+                                    std::cmp::Ordering::Greater => Some((next_id, next_sims)),
+                                    std::cmp::Ordering::Less => {
+                                        Some((most_visited_id, most_visited_sims))
+                                    }
+                                    // Break ties randomly instead of always favoring
+                                    // whichever child was expanded first.
+                                    std::cmp::Ordering::Equal => {
+                                        if self.rng.gen_bool(0.5) {
+                                            Some((next_id, next_sims))
+                                        } else {
+                                            Some((most_visited_id, most_visited_sims))
+                                        }
+                                    }
+                                }
+                            }
+                            None => Some((next_id, next_sims)),
+                        }
+                    },
+                )
+                .map(|(id, _)| id)
+        })?;
         let best_move = self[best_child_id]
             .last_move
             .clone()
@@ -193,38 +553,447 @@ pub enum Until {
     Iterations(usize),
 }
 
+impl Until {
+    fn keep_going(&self, start: Instant, iterations: usize) -> bool {
+        match *self {
+            Until::Milliseconds(max_milliseconds) => {
+                start.elapsed() < Duration::from_millis(max_milliseconds)
+            }
+            Until::Iterations(max_iterations) => iterations < max_iterations,
+        }
+    }
+}
+
 pub struct Engine {
     tree: Tree,
+    heuristic: Arc<dyn Heuristic>,
+    /// Seed for every tree's RNG, including the per-thread trees spawned by
+    /// the parallel `go`. `None` means each tree seeds itself from entropy,
+    /// so runs stay nondeterministic unless a seed is supplied.
+    seed: Option<u64>,
+    /// Number of independent trees searched in parallel by `go`, aggregated
+    /// via root parallelization. Defaults to the machine's parallelism.
+    pub threads: usize,
 }
 
 impl Engine {
     pub fn new(setup: &dyn Setup) -> Result<Engine, BughousePositionError> {
+        Self::with_options(setup, Box::new(ZeroHeuristic), None)
+    }
+
+    pub fn with_heuristic(
+        setup: &dyn Setup,
+        heuristic: Box<dyn Heuristic>,
+    ) -> Result<Engine, BughousePositionError> {
+        Self::with_options(setup, heuristic, None)
+    }
+
+    /// Builds an engine whose search is reproducible: a fixed `seed` makes
+    /// every `execute_mcts` rollout, child pick and tie-break deterministic,
+    /// so the same position, seed and `threads` produce the same `go` result
+    /// under an `Until::Iterations` budget. An `Until::Milliseconds` budget
+    /// runs a time-dependent number of iterations, so its result can vary.
+    pub fn with_options(
+        setup: &dyn Setup,
+        heuristic: Box<dyn Heuristic>,
+        seed: Option<u64>,
+    ) -> Result<Engine, BughousePositionError> {
         let position = Bughouse::from_setup(setup, CastlingMode::Standard)?;
-        let root = Node {
-            last_move: None,
-            side_that_moved: position.turn().not(),
-            position,
-            wins: 0f32,
-            simulations: 0,
-            children: vec![],
-        };
+        let heuristic: Arc<dyn Heuristic> = Arc::from(heuristic);
+        let threads = std::thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1);
         Ok(Engine {
-            tree: Tree::new(root),
+            tree: Tree::new(Node::root(position), Arc::clone(&heuristic), seed),
+            heuristic,
+            seed,
+            threads,
         })
     }
+
+    // Root parallelization: search `self.threads` independent trees (each
+    // starting from the same root position) concurrently, then sum their
+    // root-move visit counts and play the move with the highest total.
     pub fn go(&mut self, until: Until) -> Option<Move> {
+        if self.threads <= 1 {
+            return self.go_single_threaded(until);
+        }
+
+        let root_position = self.tree.root_position().clone();
+        // Let one worker carry on from `self.tree` instead of every worker
+        // starting from a bare, unsearched root -- otherwise a `go` that
+        // follows a `play` would throw away exactly the accumulated search
+        // that `play` just re-rooted. The rest still start fresh, same as
+        // before.
+        let continued_tree = std::mem::replace(
+            &mut self.tree,
+            Tree::new(Node::root(root_position.clone()), Arc::clone(&self.heuristic), self.seed),
+        );
+        let mut seed_trees: Vec<Option<Tree>> = (0..self.threads).map(|_| None).collect();
+        seed_trees[0] = Some(continued_tree);
+
+        // Keep every searched tree around, not just its stats: whichever one
+        // ends up having explored the chosen move the most becomes the new
+        // `self.tree`, so the next `go`/`play` builds on this search instead
+        // of starting from a bare, unsearched root.
+        let per_thread_trees: Vec<Tree> = seed_trees
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, seed_tree)| {
+                // Derive a distinct seed per thread so a seeded `Engine`
+                // still produces a reproducible result, rather than every
+                // tree exploring identically.
+                let thread_seed = self.seed.map(|seed| seed.wrapping_add(i as u64));
+                let mut tree = seed_tree.unwrap_or_else(|| {
+                    Tree::new(
+                        Node::root(root_position.clone()),
+                        Arc::clone(&self.heuristic),
+                        thread_seed,
+                    )
+                });
+                let start = Instant::now();
+                let mut iterations = 0;
+                while until.keep_going(start, iterations) {
+                    tree.execute_mcts();
+                    iterations += 1;
+                }
+                tree
+            })
+            .collect();
+        let per_thread_stats: Vec<Vec<(Option<ProvenValue>, i32)>> = per_thread_trees
+            .iter()
+            .map(Tree::root_child_stats)
+            .collect();
+
+        // Every tree expands the same root position, so its legal moves
+        // come out in the same deterministic order; children line up
+        // index-for-index across trees, so they can be summed/compared
+        // directly.
+        let moves = root_position.legal_moves();
+        let move_count = moves.len();
+        // A proven win settles the question outright, mirroring
+        // `Tree::best_move`'s single-threaded behavior: it doesn't matter
+        // that some other move racked up more total visits across threads
+        // if even one thread proved a forced win.
+        let proven_win_index = (0..move_count).find(|&i| {
+            per_thread_stats
+                .iter()
+                .any(|stats| matches!(stats.get(i), Some((Some(ProvenValue::Win), _))))
+        });
+        let best_index = match proven_win_index {
+            Some(i) => i,
+            None => (0..move_count).max_by_key(|&i| -> i32 {
+                per_thread_stats
+                    .iter()
+                    .map(|stats| stats.get(i).map(|&(_, sims)| sims).unwrap_or(0))
+                    .sum()
+            })?,
+        };
+        let best_move = moves[best_index].clone();
+
+        // Adopt whichever thread's tree explored `best_move` the most as the
+        // engine's tree going forward, so `Engine::play` can re-root it
+        // instead of always falling back to a fresh, unsearched root.
+        if let Some(winning_tree) = per_thread_trees.into_iter().max_by_key(|tree| {
+            tree.root_child_stats()
+                .get(best_index)
+                .map(|&(_, sims)| sims)
+                .unwrap_or(0)
+        }) {
+            self.tree = winning_tree;
+        }
+
+        Some(best_move)
+    }
+
+    fn go_single_threaded(&mut self, until: Until) -> Option<Move> {
         let start = Instant::now();
         let mut iterations = 0;
-        while match until {
-            Until::Milliseconds(max_milliseconds) => {
-                start.elapsed() < Duration::from_millis(max_milliseconds)
-            }
-            Until::Iterations(max_iterations) => iterations < max_iterations,
-        } {
+        while until.keep_going(start, iterations) {
             self.tree.execute_mcts();
             iterations += 1;
         }
-        println!("iterations: {}", iterations);
         self.tree.best_move()
     }
+
+    /// Plays `mv`, re-rooting the search tree at the child that already
+    /// explored it instead of discarding everything and starting over.
+    /// Every simulation spent on that subtree carries forward into the
+    /// next `go`.
+    pub fn play(&mut self, mv: &Move) {
+        self.tree.play(mv);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use shakmaty::fen::Fen;
+
+    use super::*;
+
+    // The hardcoded rook-endgame FEN from `main`: white to move and mate in
+    // one (Rd5-d8# -- the rook check on the back rank, with the king on g6
+    // covering g7/h7 and the rook itself covering g8).
+    const ROOK_ENDGAME_FEN: &str = "7k/8/b5K1/3R4/8/1P4P1/8/8 w - - 0 1";
+
+    fn rook_endgame_fen() -> Fen {
+        Fen::from_str(ROOK_ENDGAME_FEN).expect("valid fen")
+    }
+
+    fn rook_endgame() -> Bughouse {
+        Bughouse::from_setup(&rook_endgame_fen(), CastlingMode::Standard).expect("valid position")
+    }
+
+    // The ordinary starting position: unlike `ROOK_ENDGAME_FEN` it isn't a
+    // forced mate, so a search over it actually keeps distributing visits
+    // across plies instead of proving itself out after one iteration --
+    // needed for tests that care about visit counts accumulating over time.
+    const STARTING_POSITION_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/8/RNBQKBNR w KQkq - 0 1";
+
+    fn starting_position_fen() -> Fen {
+        Fen::from_str(STARTING_POSITION_FEN).expect("valid fen")
+    }
+
+    // A position reached by playing whichever legal move mates immediately,
+    // for tests that need a ready-made terminal leaf.
+    fn terminal_position() -> Bughouse {
+        let position = rook_endgame();
+        position
+            .legal_moves()
+            .iter()
+            .find_map(|mv| {
+                let next = position.clone().play(mv).expect("legal move");
+                next.outcome().is_some().then_some(next)
+            })
+            .expect("the rook endgame has at least one mating reply")
+    }
+
+    // A heuristic whose rollout weights are always zero, as a bughouse-aware
+    // heuristic that only favors checks/captures/drops would return for a
+    // quiet position with none of those.
+    struct AllZeroWeightHeuristic;
+
+    impl Heuristic for AllZeroWeightHeuristic {
+        fn move_weight(&self, _position: &Bughouse, _mv: &Move) -> f32 {
+            0.0
+        }
+
+        fn prior(&self, _position: &Bughouse, _mv: &Move) -> (f32, i32) {
+            (3.0, 4)
+        }
+    }
+
+    #[test]
+    fn expand_tree_seeds_children_from_heuristic_prior() {
+        let mut tree = Tree::new(
+            Node::root(rook_endgame()),
+            Arc::new(AllZeroWeightHeuristic),
+            Some(1),
+        );
+        tree.expand_tree(NodeId(0));
+        let root = &tree[NodeId(0)];
+        assert!(!root.children.is_empty());
+        for &child_id in &root.children {
+            assert_eq!(tree[child_id].wins, 3.0);
+            assert_eq!(tree[child_id].simulations, 4);
+        }
+    }
+
+    #[test]
+    fn simulate_falls_back_to_an_unweighted_pick_when_every_move_weighs_zero() {
+        let position = rook_endgame();
+        let mut tree = Tree::new(
+            Node::root(position.clone()),
+            Arc::new(AllZeroWeightHeuristic),
+            Some(7),
+        );
+        // Before the fix, a heuristic scoring every move 0.0 made
+        // `choose_weighted` return `Err`, which fell through to a spurious
+        // "no legal moves" panic on this (very legal) quiet position.
+        let _ = tree.simulate(&position);
+    }
+
+    #[test]
+    fn parallel_go_picks_a_legal_move() {
+        let mut engine =
+            Engine::with_options(&rook_endgame_fen(), Box::new(ZeroHeuristic), Some(42))
+                .expect("engine");
+        engine.threads = 4;
+        let mv = engine
+            .go(Until::Iterations(50))
+            .expect("a move should be found");
+        assert!(rook_endgame().legal_moves().iter().any(|legal| *legal == mv));
+    }
+
+    #[test]
+    fn go_then_play_then_go_reuses_the_tree_under_the_default_multithreaded_engine() {
+        let mut engine =
+            Engine::with_options(&starting_position_fen(), Box::new(ZeroHeuristic), Some(17))
+                .expect("engine");
+        // Exercise the default (multi-threaded) path explicitly, regardless
+        // of how many cores this machine happens to have.
+        engine.threads = 2;
+
+        let first_move = engine
+            .go(Until::Iterations(200))
+            .expect("a move should be found");
+        engine.play(&first_move);
+        let carried_simulations = engine.tree[NodeId(0)].simulations;
+        assert!(
+            carried_simulations > 0,
+            "play() should have re-rooted onto a child with simulations carried over from the first go()"
+        );
+
+        engine.go(Until::Iterations(5));
+        let simulations_after_second_go = engine.tree[NodeId(0)].simulations;
+
+        // A worker starting from a bare root could rack up at most 5
+        // simulations on the new root in this tiny second budget; seeing
+        // more than that proves a worker picked up `self.tree`'s
+        // accumulated visits instead of every worker discarding it and
+        // starting over, which is exactly what `play()`'s re-rooting would
+        // otherwise be wasted effort for.
+        assert!(
+            simulations_after_second_go > 5,
+            "expected the second go() to build on simulations carried over from the first, got {}",
+            simulations_after_second_go
+        );
+    }
+
+    #[test]
+    fn mcts_solver_marks_the_mating_child_as_a_proven_win() {
+        let mut tree = Tree::new(Node::root(rook_endgame()), Arc::new(ZeroHeuristic), Some(11));
+        tree.execute_mcts();
+        let root = &tree[NodeId(0)];
+        let mating_child_id = root
+            .children
+            .iter()
+            .copied()
+            .find(|&child_id| tree[child_id].proven == Some(ProvenValue::Win))
+            .expect("expanding the root endgame position should immediately prove one mating reply");
+        let mating_move = tree[mating_child_id]
+            .last_move
+            .clone()
+            .expect("child has a last_move");
+        let mated = rook_endgame().play(&mating_move).expect("legal move");
+        assert_eq!(
+            mated.outcome(),
+            Some(Outcome::Decisive {
+                winner: Color::White
+            })
+        );
+    }
+
+    #[test]
+    fn backpropagate_credits_amaf_for_moves_played_later_in_the_same_simulation() {
+        let root_position = rook_endgame();
+        let side_to_move = root_position.turn();
+        let legal = root_position.legal_moves();
+        assert!(legal.len() >= 2, "test position needs at least two legal moves");
+        let move_a = legal[0].clone();
+        let move_b = legal[1].clone();
+
+        let mut tree = Tree::new(
+            Node::root(root_position.clone()),
+            Arc::new(ZeroHeuristic),
+            Some(0),
+        );
+        for mv in [&move_a, &move_b] {
+            let child_position = root_position.clone().play(mv).expect("legal move");
+            let proven = child_position
+                .outcome()
+                .map(|outcome| ProvenValue::of(outcome, side_to_move));
+            let child = Node {
+                last_move: Some(mv.clone()),
+                side_that_moved: side_to_move,
+                position: child_position,
+                wins: 0.0,
+                simulations: 0,
+                children: vec![],
+                amaf: vec![],
+                proven,
+            };
+            let child_id = tree.push_node(child);
+            tree[NodeId(0)].children.push(child_id);
+            tree[NodeId(0)].amaf.push(AmafStats::default());
+        }
+
+        // A rollout that never actually descends into the child for
+        // `move_b`, but plays it later in the same simulation, should still
+        // credit that child's AMAF stats -- that's the entire point of RAVE.
+        let rollout_moves = vec![(side_to_move, move_b.clone())];
+        tree.backpropagate(
+            &[NodeId(0)],
+            Outcome::Decisive {
+                winner: side_to_move,
+            },
+            &rollout_moves,
+        );
+
+        let root = &tree[NodeId(0)];
+        assert_eq!(root.amaf[0].visits, 0);
+        assert_eq!(root.amaf[1].visits, 1);
+        assert_eq!(root.amaf[1].wins, 1.0);
+    }
+
+    #[test]
+    fn play_rebases_tree_and_remaps_descendant_children_to_new_indices() {
+        let mut tree = Tree::new(Node::root(rook_endgame()), Arc::new(ZeroHeuristic), Some(2));
+        tree.expand_tree(NodeId(0));
+        let chosen_child_id = tree[NodeId(0)].children[0];
+        tree.expand_tree(chosen_child_id);
+        let chosen_move = tree[chosen_child_id]
+            .last_move
+            .clone()
+            .expect("child has a last_move");
+        let grandchild_moves: Vec<Move> = tree[chosen_child_id]
+            .children
+            .iter()
+            .map(|&id| tree[id].last_move.clone().expect("grandchild has a last_move"))
+            .collect();
+
+        tree.play(&chosen_move);
+
+        let new_root = &tree[NodeId(0)];
+        let new_children_moves: Vec<Move> = new_root
+            .children
+            .iter()
+            .map(|&id| tree[id].last_move.clone().expect("child has a last_move"))
+            .collect();
+        assert_eq!(new_children_moves, grandchild_moves);
+        assert_eq!(tree.nodes.len(), 1 + grandchild_moves.len());
+    }
+
+    #[test]
+    fn seeded_engine_deterministically_finds_the_forced_mate() {
+        let find_move = || {
+            Engine::with_options(&rook_endgame_fen(), Box::new(ZeroHeuristic), Some(123))
+                .expect("engine")
+                .go(Until::Iterations(50))
+                .expect("a move should be found")
+        };
+        let first = find_move();
+        let second = find_move();
+        assert_eq!(first, second);
+
+        let mated = rook_endgame().play(&first).expect("legal move");
+        assert_eq!(
+            mated.outcome(),
+            Some(Outcome::Decisive {
+                winner: Color::White
+            })
+        );
+    }
+
+    #[test]
+    fn execute_mcts_scores_a_terminal_leaf_directly_instead_of_expanding_it() {
+        let mut tree = Tree::new(Node::root(terminal_position()), Arc::new(ZeroHeuristic), Some(3));
+        tree.execute_mcts();
+        let root = &tree[NodeId(0)];
+        assert!(root.children.is_empty());
+        assert_eq!(root.simulations, 1);
+        assert!(root.proven.is_some());
+    }
 }
@@ -0,0 +1,33 @@
+use shakmaty::Move;
+
+use crate::board::Bughouse;
+
+/// Domain-specific knowledge that can be injected into the search without
+/// touching the MCTS core. Implementors score candidate moves for weighted
+/// rollout selection and can bias the prior value of freshly expanded nodes,
+/// letting e.g. a bughouse-aware heuristic favor checks, captures, and drops
+/// near the enemy king instead of playing uniformly at random.
+pub trait Heuristic: Send + Sync {
+    /// Relative weight of playing `mv` from `position` during a random
+    /// rollout. Must not be negative; higher means more likely to be chosen.
+    /// If every legal move weighs zero, the rollout picks uniformly instead.
+    fn move_weight(&self, position: &Bughouse, mv: &Move) -> f32;
+
+    /// Prior `(wins, simulations)` used to seed a child node the moment it is
+    /// created by `expand_tree`, before any rollouts have reached it.
+    fn prior(&self, position: &Bughouse, mv: &Move) -> (f32, i32);
+}
+
+/// A [`Heuristic`] with no domain knowledge: every move is equally likely in
+/// rollouts and nodes start with no prior bias, recovering plain MCTS.
+pub struct ZeroHeuristic;
+
+impl Heuristic for ZeroHeuristic {
+    fn move_weight(&self, _position: &Bughouse, _mv: &Move) -> f32 {
+        1.0
+    }
+
+    fn prior(&self, _position: &Bughouse, _mv: &Move) -> (f32, i32) {
+        (0.0, 0)
+    }
+}
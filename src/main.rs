@@ -8,6 +8,7 @@ use shakmaty::{
 
 mod board;
 mod engine;
+mod heuristic;
 
 fn main() {
     let fen = Fen::from_str("7k/8/b5K1/3R4/8/1P4P1/8/8 w - - 0 1").expect("invalid fen");